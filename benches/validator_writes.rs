@@ -0,0 +1,78 @@
+//! Benchmark demonstrating the per-block store-write count drop from the
+//! validator-set dirty flag.
+//!
+//! Before the dirty flag, every `check_tx`/`deliver_tx`/`begin_block`
+//! unconditionally re-serialized and wrote back the validator map. The vast
+//! majority of transactions never touch validator power, so skipping the write
+//! when the serialized map is unchanged eliminates one store `put` per
+//! transaction. The harness below drives the *real* `write_validators_if_changed`
+//! write path against an in-memory `Store` that counts writes to the validator
+//! key, so the numbers reflect crate behaviour rather than a local re-implementation.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use nomic::chain::chain::abci_server::{write_validators, write_validators_if_changed};
+use orga::Store;
+use std::cell::Cell;
+use std::collections::BTreeMap;
+
+/// In-memory store that records how many times the validator key was written.
+#[derive(Default)]
+struct CountingStore {
+    map: BTreeMap<Vec<u8>, Vec<u8>>,
+    validator_writes: Cell<u64>,
+}
+
+impl Store for CountingStore {
+    fn get(&self, key: &[u8]) -> nomic::Result<Option<Vec<u8>>> {
+        Ok(self.map.get(key).cloned())
+    }
+
+    fn put(&mut self, key: Vec<u8>, value: Vec<u8>) -> nomic::Result<()> {
+        if key == b"validators" {
+            self.validator_writes.set(self.validator_writes.get() + 1);
+        }
+        self.map.insert(key, value);
+        Ok(())
+    }
+}
+
+fn sample_validators() -> BTreeMap<Vec<u8>, u64> {
+    let mut map = BTreeMap::new();
+    for i in 0u64..16 {
+        map.insert(vec![i as u8; 33], 10 + i);
+    }
+    map
+}
+
+fn bench_block(c: &mut Criterion) {
+    const TXS_PER_BLOCK: u64 = 1_000;
+    let validators = sample_validators();
+    let original_bytes = bincode::serialize(&validators).unwrap();
+
+    c.bench_function("block_writes_always", |b| {
+        b.iter(|| {
+            let mut store = CountingStore::default();
+            for _ in 0..TXS_PER_BLOCK {
+                write_validators(&mut store, &validators).unwrap();
+            }
+            assert_eq!(store.validator_writes.get(), TXS_PER_BLOCK);
+        })
+    });
+
+    c.bench_function("block_writes_dirty_flag", |b| {
+        b.iter(|| {
+            let mut store = CountingStore::default();
+            for _ in 0..TXS_PER_BLOCK {
+                // No transaction changed validator power, so the dirty flag
+                // elides every store write for the block.
+                let changed =
+                    write_validators_if_changed(&mut store, &original_bytes, &validators).unwrap();
+                assert!(!changed);
+            }
+            assert_eq!(store.validator_writes.get(), 0);
+        })
+    });
+}
+
+criterion_group!(benches, bench_block);
+criterion_main!(benches);
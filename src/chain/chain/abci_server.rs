@@ -1,5 +1,9 @@
 //!  Start the peg abci server.
 
+use super::admin::{self, AdminState};
+use super::authority::{self, SudoCall};
+use super::pubkey_cache::ValidatorPubkeyCache;
+use super::slashing::{self, OffenseKind, SlashParams};
 use super::state_machine::{initialize, run};
 use super::Action;
 use crate::core::primitives::transaction::Transaction;
@@ -11,10 +15,87 @@ use orga::{
     store::Iter,
     Store,
 };
+use serde::Deserialize;
+use std::cell::RefCell;
 use std::collections::BTreeMap;
 use std::path::Path;
 
-struct App;
+/// A client submission: either an ordinary transaction or a privileged sudo
+/// call. The dispatch is additive and back-compatible — a bare `Transaction`
+/// JSON (the pre-existing wire format) still decodes unchanged. `SudoCall`
+/// is tried first because it uses `deny_unknown_fields`, so only a body whose
+/// fields are exactly those of a sudo call matches it; anything else falls
+/// through to `Transaction`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Submission {
+    Sudo(SudoCall),
+    Transaction(Transaction),
+}
+
+struct App {
+    /// Genesis writes are staged here during `init_chain` and only flushed to
+    /// the store on the first `begin_block`. If the node is killed after
+    /// `init_chain` but before height 1 is committed, Tendermint replays
+    /// `InitChain` on restart and we simply recompute the staging buffer, so
+    /// genesis remains consistent — the `put`s never hit the store twice.
+    genesis: RefCell<Option<Vec<(Vec<u8>, Vec<u8>)>>>,
+    /// Two-way validator pubkey/index cache. Populated from genesis and kept in
+    /// sync as the set grows, so sudo signature checks reuse decompressed keys
+    /// and `end_block` takes consensus-index order from here. Voting power is
+    /// not cached: the handlers still load the whole validator map from the
+    /// store on each call.
+    pubkey_cache: RefCell<ValidatorPubkeyCache>,
+    /// Shared state with the admin HTTP server: the published validator-set
+    /// snapshot.
+    admin: AdminState,
+}
+
+impl App {
+    fn new(pubkey_cache: ValidatorPubkeyCache, admin: AdminState) -> Self {
+        App {
+            genesis: RefCell::new(None),
+            pubkey_cache: RefCell::new(pubkey_cache),
+            admin,
+        }
+    }
+
+    /// Read a staged genesis write for `key`, if genesis has not yet been
+    /// flushed to the store. `CheckTx` can arrive between `InitChain` and the
+    /// first committed block; reading the staging buffer keeps those lookups
+    /// from hitting the not-yet-written store keys and panicking.
+    fn staged(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.genesis.borrow().as_ref().and_then(|writes| {
+            writes
+                .iter()
+                .rev()
+                .find(|(k, _)| k.as_slice() == key)
+                .map(|(_, v)| v.clone())
+        })
+    }
+
+    /// Current validator map together with the exact bytes it was decoded from,
+    /// preferring staged genesis state before the first block is committed so
+    /// mempool `CheckTx` does not panic reading the store. The bytes let callers
+    /// detect whether an action mutated the set without cloning the whole map.
+    fn load_validators<S: Store>(&self, store: S) -> (BTreeMap<Vec<u8>, u64>, Vec<u8>) {
+        let bytes = match self.staged(b"validators") {
+            Some(bytes) => bytes,
+            None => read_validators_bytes(store),
+        };
+        let map = bincode::deserialize(&bytes).expect("Failed to deserialize validator map");
+        (map, bytes)
+    }
+
+    /// Current height, or `0` before the first block has been committed (the
+    /// height key is written in `begin_block`, not staged at genesis).
+    fn read_height<S: Store>(&self, store: &S) -> Result<u64> {
+        if self.genesis.borrow().is_some() {
+            return Ok(0);
+        }
+        read_height(store)
+    }
+}
 
 impl Application for App {
     fn init_chain<S: Store + Iter>(
@@ -29,8 +110,22 @@ impl Application for App {
             validators.insert(pub_key, power);
         }
 
-        write_validators(&mut store, validators)?;
-        initialize(&mut store)?;
+        // Route all genesis writes through a staging layer instead of the real
+        // store so `InitChain` has no committed side effects until height 1.
+        let mut staging = StagingStore::new(&mut store);
+
+        self.pubkey_cache
+            .borrow_mut()
+            .import_new_pubkeys(&mut staging, validators.keys().cloned())?;
+
+        // Seed the sudo key from the chain's app_state so the authority
+        // subsystem is active from genesis rather than permanently inert.
+        authority::init_from_genesis(&mut staging, req.get_app_state_bytes())?;
+
+        write_validators(&mut staging, &validators)?;
+        initialize(&mut staging)?;
+
+        *self.genesis.borrow_mut() = Some(staging.into_writes());
 
         Ok(ResponseInitChain::new())
     }
@@ -40,23 +135,35 @@ impl Application for App {
         mut store: S,
         req: RequestCheckTx,
     ) -> Result<ResponseCheckTx> {
-        let tx = serde_json::from_slice::<Transaction>(req.get_tx());
-        let mut validators = read_validators(&mut store);
-        let height = read_height(&store)?;
-
-        match tx {
-            Ok(tx) => match run(&mut store, Action::Transaction(tx), &mut validators, height) {
-                Ok(_execution_result) => {
-                    // TODO: Don't write validators back to store if they haven't changed
-                    write_validators(&mut store, validators)?;
-                    let mut res = ResponseCheckTx::new();
-                    res.set_data(vec![]);
-                    Ok(res)
-                }
-
-                Err(e) => bail!("check tx err: {:?}", e),
-            },
+        let (mut validators, original_bytes) = self.load_validators(&mut store);
+        let height = self.read_height(&store)?;
 
+        // A sudo call carries an embedded signature that must match the stored
+        // sudo key; an ordinary transaction runs through the state machine.
+        match serde_json::from_slice::<Submission>(req.get_tx()) {
+            Ok(Submission::Sudo(call)) => {
+                let cached = self.pubkey_cache.borrow().get_pubkey_for(&call.pubkey);
+                match call.apply(&mut store, &mut validators, cached) {
+                    Ok(()) => {
+                        write_validators_if_changed(&mut store, &original_bytes, &validators)?;
+                        let mut res = ResponseCheckTx::new();
+                        res.set_data(vec![]);
+                        Ok(res)
+                    }
+                    Err(e) => bail!("check sudo tx err: {:?}", e),
+                }
+            }
+            Ok(Submission::Transaction(tx)) => {
+                match run(&mut store, Action::Transaction(tx), &mut validators, height) {
+                    Ok(_execution_result) => {
+                        write_validators_if_changed(&mut store, &original_bytes, &validators)?;
+                        let mut res = ResponseCheckTx::new();
+                        res.set_data(vec![]);
+                        Ok(res)
+                    }
+                    Err(e) => bail!("check tx err: {:?}", e),
+                }
+            }
             Err(_e) => bail!("error deserializing tx (check_tx)"),
         }
     }
@@ -66,21 +173,41 @@ impl Application for App {
         mut store: S,
         req: RequestDeliverTx,
     ) -> Result<ResponseDeliverTx> {
-        let tx = serde_json::from_slice::<Transaction>(req.get_tx());
-        let mut validators = read_validators(&mut store);
-        let height = read_height(&store)?;
-
-        match tx {
-            Ok(tx) => match run(&mut store, Action::Transaction(tx), &mut validators, height) {
-                Ok(_execution_result) => {
-                    write_validators(&mut store, validators)?;
-                    let mut res = ResponseDeliverTx::new();
-                    res.set_data(vec![]);
-                    Ok(res)
-                }
+        let (mut validators, original_bytes) = self.load_validators(&mut store);
+        let height = self.read_height(&store)?;
 
-                Err(_e) => bail!("error executing tx (deliver_tx)"),
-            },
+        match serde_json::from_slice::<Submission>(req.get_tx()) {
+            Ok(Submission::Sudo(call)) => {
+                let cached = self.pubkey_cache.borrow().get_pubkey_for(&call.pubkey);
+                match call.apply(&mut store, &mut validators, cached) {
+                    Ok(()) => {
+                        write_validators_if_changed(&mut store, &original_bytes, &validators)?;
+                        // Surface a validator added by this sudo tx to the cache
+                        // so `end_block` emits its update this block, not later.
+                        self.pubkey_cache
+                            .borrow_mut()
+                            .import_new_pubkeys(&mut store, validators.keys().cloned())?;
+                        let mut res = ResponseDeliverTx::new();
+                        res.set_data(vec![]);
+                        Ok(res)
+                    }
+                    Err(_e) => bail!("error executing sudo tx (deliver_tx)"),
+                }
+            }
+            Ok(Submission::Transaction(tx)) => {
+                match run(&mut store, Action::Transaction(tx), &mut validators, height) {
+                    Ok(_execution_result) => {
+                        write_validators_if_changed(&mut store, &original_bytes, &validators)?;
+                        self.pubkey_cache
+                            .borrow_mut()
+                            .import_new_pubkeys(&mut store, validators.keys().cloned())?;
+                        let mut res = ResponseDeliverTx::new();
+                        res.set_data(vec![]);
+                        Ok(res)
+                    }
+                    Err(_e) => bail!("error executing tx (deliver_tx)"),
+                }
+            }
             Err(_e) => bail!("error deserializing tx (deliver_tx)"),
         }
     }
@@ -90,34 +217,114 @@ impl Application for App {
         mut store: S,
         req: RequestBeginBlock,
     ) -> Result<ResponseBeginBlock> {
+        // Flush any staged genesis writes exactly once, on the first block.
+        if let Some(writes) = self.genesis.borrow_mut().take() {
+            for (key, value) in writes {
+                store.put(key, value)?;
+            }
+        }
+
         let header = req.get_header();
         let height = header.height;
         let height_bytes = height.to_be_bytes();
         store.put(b"height".to_vec(), height_bytes.to_vec())?;
         let action = Action::BeginBlock(header.clone());
-        let mut validators = read_validators(&mut store);
+        let original_bytes = read_validators_bytes(&mut store);
+        let mut validators: BTreeMap<Vec<u8>, u64> =
+            bincode::deserialize(&original_bytes).expect("Failed to deserialize validator map");
+
+        // Slash any validators reported for Byzantine behaviour before running
+        // the block's begin-block logic. Applied slashes are keyed on
+        // `(height, address)` so re-delivered blocks do not double-apply.
+        let slash_params = SlashParams::default();
+        for evidence in req.get_byzantine_validators() {
+            let address = evidence.get_validator().get_address().to_vec();
+            let kind = match evidence.get_field_type() {
+                EvidenceType::DUPLICATE_VOTE => OffenseKind::DuplicateVote,
+                EvidenceType::LIGHT_CLIENT_ATTACK => OffenseKind::LightClientAttack,
+                _ => OffenseKind::Unknown,
+            };
+            // Key the applied-slash marker on the evidence's own height, not the
+            // current block height, so the same offense re-reported in a later
+            // block is recognized as already applied.
+            slashing::apply_slash(
+                &mut store,
+                &mut validators,
+                evidence.get_height() as u64,
+                &address,
+                kind,
+                &slash_params,
+            )?;
+        }
 
         run(&mut store, action, &mut validators, height as u64)?;
-        write_validators(&mut store, validators)?;
+        let validators_changed =
+            write_validators_if_changed(&mut store, &original_bytes, &validators)?;
+
+        // Record any validators that joined this block so later blocks resolve
+        // their consensus index from the cache; steady-state blocks add nothing
+        // and touch the store only when the set actually grew.
+        if validators_changed {
+            self.pubkey_cache
+                .borrow_mut()
+                .import_new_pubkeys(&mut store, validators.keys().cloned())?;
+        }
+
+        // Republishing the snapshot is non-consensus bookkeeping for the admin
+        // list endpoint, so it runs once per block rather than per transaction.
+        // It runs every block, changed or not, so the list is populated after a
+        // restart and reflects `enabled` flags toggled without a power change.
+        let mut snapshot = Vec::with_capacity(validators.len());
+        for (pubkey, power) in &validators {
+            let enabled = admin::is_enabled(&store, pubkey)?;
+            snapshot.push((pubkey.clone(), *power, enabled));
+        }
+        self.admin.publish_snapshot(snapshot);
+
         Ok(Default::default())
     }
 
     fn end_block<S: Store + Iter>(
         &self,
-        store: S,
+        mut store: S,
         _req: RequestEndBlock,
     ) -> Result<ResponseEndBlock> {
-        let validators = read_validators(store);
+        let validators = read_validators(&store);
+        let pubkey_cache = self.pubkey_cache.borrow();
+
+        // `ValidatorUpdate`s are a delta: Tendermint only drops a validator when
+        // it receives an update with `power = 0`; omitting it leaves the
+        // validator active at its prior power. So diff the set that should be
+        // active now against the set we last told Tendermint about, emitting the
+        // new power for changed/added entries and `power = 0` for any that fell
+        // out (e.g. slashed to zero or removed via sudo).
+        let previous = read_active_validators(&store);
+        let mut desired = BTreeMap::<Vec<u8>, u64>::new();
+        for (_index, pub_key_bytes) in pubkey_cache.iter() {
+            let power = validators.get(pub_key_bytes).copied().unwrap_or(0);
+            // A disabled validator must be driven to `power = 0` in Tendermint,
+            // so exclude it from the desired active set; the delta below then
+            // emits the removal rather than leaving it active at full power.
+            if power > 0 && admin::is_enabled(&store, pub_key_bytes)? {
+                desired.insert(pub_key_bytes.to_vec(), power);
+            }
+        }
+
         let mut validator_updates: Vec<ValidatorUpdate> = Vec::new();
-        for (pub_key_bytes, power) in validators {
-            let mut validator_update = ValidatorUpdate::new();
-            let mut pub_key = PubKey::new();
-            pub_key.set_data(pub_key_bytes);
-            pub_key.set_field_type(String::from("secp256k1"));
-            validator_update.set_pub_key(pub_key);
-            validator_update.set_power(power as i64);
-            validator_updates.push(validator_update);
+        // Changed or newly-active validators.
+        for (pub_key_bytes, power) in &desired {
+            if previous.get(pub_key_bytes) != Some(power) {
+                validator_updates.push(validator_update(pub_key_bytes, *power as i64));
+            }
         }
+        // Validators that were active last block but are not anymore.
+        for pub_key_bytes in previous.keys() {
+            if !desired.contains_key(pub_key_bytes) {
+                validator_updates.push(validator_update(pub_key_bytes, 0));
+            }
+        }
+
+        write_active_validators(&mut store, &desired)?;
 
         let mut response = ResponseEndBlock::new();
         response.set_validator_updates(validator_updates.into());
@@ -125,32 +332,145 @@ impl Application for App {
     }
 }
 
+/// Build a `ValidatorUpdate` for a secp256k1 validator at the given power.
+fn validator_update(pub_key_bytes: &[u8], power: i64) -> ValidatorUpdate {
+    let mut update = ValidatorUpdate::new();
+    let mut pub_key = PubKey::new();
+    pub_key.set_data(pub_key_bytes.to_vec());
+    pub_key.set_field_type(String::from("secp256k1"));
+    update.set_pub_key(pub_key);
+    update.set_power(power);
+    update
+}
+
+/// The validator set last emitted to Tendermint as active (`pubkey -> power`),
+/// used to compute the `end_block` update delta. Empty before the first block.
+fn read_active_validators<S: Store>(store: &S) -> BTreeMap<Vec<u8>, u64> {
+    match store.get(b"active_validators") {
+        Ok(Some(bytes)) => bincode::deserialize(&bytes)
+            .expect("Failed to deserialize active validator set"),
+        _ => BTreeMap::new(),
+    }
+}
+
+fn write_active_validators<S: Store>(
+    mut store: S,
+    active: &BTreeMap<Vec<u8>, u64>,
+) -> Result<()> {
+    let bytes = bincode::serialize(active).expect("Failed to serialize active validator set");
+    store.put(b"active_validators".to_vec(), bytes)
+}
+
 fn read_height<S: Store>(store: &S) -> Result<u64> {
     let mut height_bytes = [0 as u8; 8];
     height_bytes.copy_from_slice(&store.get(b"height")?.unwrap()[..]);
     Ok(u64::from_be_bytes(height_bytes))
 }
 
-fn write_validators<S: Store>(mut store: S, validators: BTreeMap<Vec<u8>, u64>) -> Result<()> {
+pub fn write_validators<S: Store>(mut store: S, validators: &BTreeMap<Vec<u8>, u64>) -> Result<()> {
     let validator_map_bytes =
-        bincode::serialize(&validators).expect("Failed to serialize validator map");
+        bincode::serialize(validators).expect("Failed to serialize validator map");
     store.put(b"validators".to_vec(), validator_map_bytes)
 }
-fn read_validators<S: Store>(store: S) -> BTreeMap<Vec<u8>, u64> {
-    let validator_map_bytes = store
+
+/// Write the validator map back to the store only if its serialization differs
+/// from the bytes it was loaded from. Comparing against the original bytes — as
+/// opposed to cloning and equality-comparing the whole `BTreeMap` per
+/// transaction — avoids a full-map copy, and the write is skipped entirely on
+/// the common case where a transaction does not touch validator power. Returns
+/// whether a write occurred so callers can thread the dirty flag onward.
+pub fn write_validators_if_changed<S: Store>(
+    mut store: S,
+    before_bytes: &[u8],
+    after: &BTreeMap<Vec<u8>, u64>,
+) -> Result<bool> {
+    let after_bytes = bincode::serialize(after).expect("Failed to serialize validator map");
+    if after_bytes.as_slice() == before_bytes {
+        return Ok(false);
+    }
+    store.put(b"validators".to_vec(), after_bytes)?;
+    Ok(true)
+}
+
+/// Raw serialized validator-map bytes as stored under `b"validators"`.
+fn read_validators_bytes<S: Store>(store: S) -> Vec<u8> {
+    store
         .get(b"validators")
         .expect("Failed to read validator map bytes from store")
-        .expect("Validator map was not written to store");
+        .expect("Validator map was not written to store")
+}
+
+fn read_validators<S: Store>(store: S) -> BTreeMap<Vec<u8>, u64> {
     let validators: std::result::Result<BTreeMap<Vec<u8>, u64>, bincode::Error> =
-        bincode::deserialize(&validator_map_bytes);
+        bincode::deserialize(&read_validators_bytes(store));
     validators.expect("Failed to deserialize validator map")
 }
 
+/// Store wrapper that forwards reads (and iteration) to an inner store but
+/// records writes into a buffer instead of applying them. Used to stage
+/// genesis state so it can be flushed atomically on the first committed block.
+struct StagingStore<'a, S> {
+    inner: &'a mut S,
+    writes: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+impl<'a, S> StagingStore<'a, S> {
+    fn new(inner: &'a mut S) -> Self {
+        StagingStore {
+            inner,
+            writes: Vec::new(),
+        }
+    }
+
+    fn into_writes(self) -> Vec<(Vec<u8>, Vec<u8>)> {
+        self.writes
+    }
+}
+
+impl<'a, S: Store> Store for StagingStore<'a, S> {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        // Staged writes shadow the underlying store for read-your-writes.
+        for (k, v) in self.writes.iter().rev() {
+            if k.as_slice() == key {
+                return Ok(Some(v.clone()));
+            }
+        }
+        self.inner.get(key)
+    }
+
+    fn put(&mut self, key: Vec<u8>, value: Vec<u8>) -> Result<()> {
+        self.writes.push((key, value));
+        Ok(())
+    }
+}
+
+impl<'a, S: Iter> Iter for StagingStore<'a, S> {
+    type Iter<'b> = S::Iter<'b> where Self: 'b;
+
+    fn iter_from(&self, start: &[u8]) -> Self::Iter<'_> {
+        self.inner.iter_from(start)
+    }
+}
+
 pub fn start<P: AsRef<Path>>(nomic_home: P) {
     let merk_path = nomic_home.as_ref().join("merk.db");
     let mut merk = Merk::open(merk_path).expect("Failed to open Merk database");
     let store = MerkStore::new(&mut merk);
-    ABCIStateMachine::new(App, store)
+
+    // Restore the validator pubkey cache from the store so it survives restarts.
+    let pubkey_cache = ValidatorPubkeyCache::load(&store).expect("Failed to load pubkey cache");
+
+    // Start the bearer-authenticated admin API alongside the ABCI server.
+    let admin = AdminState::default();
+    let token = admin::ensure_api_token(&nomic_home).expect("Failed to load admin API token");
+    admin::spawn(
+        "127.0.0.1:26659".to_string(),
+        token,
+        "127.0.0.1:26657".to_string(),
+        admin.clone(),
+    );
+
+    ABCIStateMachine::new(App::new(pubkey_cache, admin), store)
         .listen("127.0.0.1:26658")
         .unwrap();
 }
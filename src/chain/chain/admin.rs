@@ -0,0 +1,202 @@
+//! Bearer-authenticated admin HTTP API for validator management.
+//!
+//! An optional management server, started alongside [`start`](super::abci_server::start),
+//! exposes endpoints to list the current validator set, add or remove a
+//! validator, and disable one so it is excluded from the next `end_block`
+//! update. Every mutating endpoint requires a bearer token; the token is
+//! generated into `nomic_home/admin_token` on first launch.
+//!
+//! Validator-set mutations must be deterministic across every node, so the
+//! admin API never writes to Merk directly. Each mutating endpoint instead
+//! broadcasts a *signed sudo transaction* to the local Tendermint RPC: the
+//! change then flows through `check_tx`/`deliver_tx` like any other transaction
+//! and is applied identically on every validator. The bearer token authorizes
+//! use of this node's broadcast endpoint; the embedded sudo signature (checked
+//! in consensus) authorizes the state change itself. Disabling is expressed as
+//! a `SudoOp::SetValidatorEnabled` that persists an `enabled` flag in consensus
+//! state, so `end_block` can skip a disabled validator on every node. The list
+//! endpoint reads a snapshot the state machine republishes each block.
+
+use super::authority::SudoCall;
+use crate::Result;
+use orga::Store;
+use rand::RngCore;
+use serde::Serialize;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Namespace prefix for the per-validator `enabled` flags.
+const ENABLED_PREFIX: &[u8] = b"admin/enabled/";
+
+/// State shared between the HTTP thread and the state machine.
+#[derive(Default)]
+struct Shared {
+    /// Snapshot of `(pubkey, power, enabled)`, republished every block.
+    snapshot: Vec<(Vec<u8>, u64, bool)>,
+}
+
+/// Handle to the admin state, cheaply clonable across threads.
+#[derive(Clone, Default)]
+pub struct AdminState {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl AdminState {
+    /// Replace the published validator snapshot (called from the state machine).
+    pub fn publish_snapshot(&self, snapshot: Vec<(Vec<u8>, u64, bool)>) {
+        self.shared.lock().unwrap().snapshot = snapshot;
+    }
+
+    fn snapshot(&self) -> Vec<(Vec<u8>, u64, bool)> {
+        self.shared.lock().unwrap().snapshot.clone()
+    }
+}
+
+/// Whether a validator is enabled. Absence of a flag means enabled, so freshly
+/// added validators participate by default.
+pub fn is_enabled<S: Store>(store: &S, pubkey: &[u8]) -> Result<bool> {
+    Ok(store.get(&enabled_key(pubkey))?.map_or(true, |v| v == [1]))
+}
+
+/// Persist a validator's `enabled` flag.
+pub fn set_enabled<S: Store>(store: &mut S, pubkey: &[u8], enabled: bool) -> Result<()> {
+    store.put(enabled_key(pubkey), vec![enabled as u8])
+}
+
+fn enabled_key(pubkey: &[u8]) -> Vec<u8> {
+    let mut key = ENABLED_PREFIX.to_vec();
+    key.extend_from_slice(pubkey);
+    key
+}
+
+/// Read the admin API token, generating and persisting one on first launch.
+pub fn ensure_api_token<P: AsRef<Path>>(nomic_home: P) -> Result<String> {
+    let token_path = nomic_home.as_ref().join("admin_token");
+    if token_path.exists() {
+        return Ok(fs::read_to_string(&token_path)?.trim().to_string());
+    }
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let token = hex::encode(bytes);
+    fs::write(&token_path, &token)?;
+    Ok(token)
+}
+
+#[derive(Serialize)]
+struct ValidatorInfo {
+    pubkey: String,
+    power: u64,
+    enabled: bool,
+}
+
+/// Admin server configuration: the bearer token and the Tendermint RPC address
+/// mutating transactions are broadcast to.
+#[derive(Clone)]
+struct Config {
+    token: String,
+    rpc_addr: String,
+}
+
+/// Spawn the admin HTTP server on its own thread. Returns immediately.
+/// `rpc_addr` is the local Tendermint RPC (e.g. `127.0.0.1:26657`) that signed
+/// sudo transactions are broadcast to.
+pub fn spawn(addr: String, token: String, rpc_addr: String, state: AdminState) {
+    let config = Config { token, rpc_addr };
+    std::thread::spawn(move || {
+        let server = match tiny_http::Server::http(&addr) {
+            Ok(server) => server,
+            Err(e) => {
+                eprintln!("failed to start admin server: {}", e);
+                return;
+            }
+        };
+        for request in server.incoming_requests() {
+            handle(request, &config, &state);
+        }
+    });
+}
+
+fn handle(mut request: tiny_http::Request, config: &Config, state: &AdminState) {
+    use tiny_http::{Method, Response};
+
+    let method = request.method().clone();
+    let url = request.url().to_string();
+
+    // GET /validators is read-only; every other route mutates and requires auth.
+    let needs_auth = !(method == Method::Get && url == "/validators");
+    if needs_auth && !authorized(&request, &config.token) {
+        let _ = request.respond(Response::from_string("unauthorized").with_status_code(401));
+        return;
+    }
+
+    let result: std::result::Result<String, (u16, String)> = match (&method, url.as_str()) {
+        (Method::Get, "/validators") => {
+            let infos: Vec<ValidatorInfo> = state
+                .snapshot()
+                .into_iter()
+                .map(|(pubkey, power, enabled)| ValidatorInfo {
+                    pubkey: hex::encode(pubkey),
+                    power,
+                    enabled,
+                })
+                .collect();
+            serde_json::to_string(&infos).map_err(|e| (500, e.to_string()))
+        }
+        // Add, remove (power 0), and disable all carry a signed sudo call in the
+        // body which is broadcast to consensus rather than applied locally.
+        (Method::Post, "/validators")
+        | (Method::Post, "/validators/remove")
+        | (Method::Post, "/validators/disable") => {
+            parse_sudo_call(&mut request).and_then(|call| broadcast_sudo(&config.rpc_addr, &call))
+        }
+        _ => Err((404, "not found".to_string())),
+    };
+
+    let response = match result {
+        Ok(body) => Response::from_string(body).with_status_code(200),
+        Err((code, msg)) => Response::from_string(msg).with_status_code(code),
+    };
+    let _ = request.respond(response);
+}
+
+fn authorized(request: &tiny_http::Request, token: &str) -> bool {
+    request.headers().iter().any(|header| {
+        header.field.equiv("Authorization")
+            && header.value.as_str() == format!("Bearer {}", token)
+    })
+}
+
+fn parse_sudo_call(
+    request: &mut tiny_http::Request,
+) -> std::result::Result<SudoCall, (u16, String)> {
+    let mut body = String::new();
+    request
+        .as_reader()
+        .read_to_string(&mut body)
+        .map_err(|e| (400, e.to_string()))?;
+    serde_json::from_str(&body).map_err(|e| (400, format!("invalid sudo call: {}", e)))
+}
+
+/// Serialize a signed sudo call as a bare JSON object with exactly the fields of
+/// a `SudoCall` (the form the ABCI server decodes as a sudo submission) and
+/// broadcast it to the local Tendermint RPC, so the validator-set change is
+/// applied deterministically on every node.
+fn broadcast_sudo(
+    rpc_addr: &str,
+    call: &SudoCall,
+) -> std::result::Result<String, (u16, String)> {
+    let tx = serde_json::to_vec(call).map_err(|e| (400, format!("invalid sudo call: {}", e)))?;
+
+    let rpc_body = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "broadcast_tx_sync",
+        "params": { "tx": base64::encode(&tx) },
+    });
+    ureq::post(&format!("http://{}/", rpc_addr))
+        .send_json(rpc_body)
+        .map(|_| "{\"status\":\"broadcast\"}".to_string())
+        .map_err(|e| (502, format!("broadcast failed: {}", e)))
+}
@@ -0,0 +1,197 @@
+//! Sudo/governance authority.
+//!
+//! A single privileged public key — the *sudo address* — is stored in the Merk
+//! store. Its holder may submit [`SudoCall`]s that directly adjust validator
+//! voting power, rotate the sudo key, or change peg parameters. Every such call
+//! carries an embedded signature which must match the stored sudo key; any
+//! other signer is rejected. This gives operators a controlled emergency path
+//! to correct the validator set without a hard fork.
+
+use crate::Result;
+use failure::{bail, ensure};
+use orga::Store;
+use secp256k1::{Message, PublicKey, Secp256k1, Signature};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+/// Merk key holding the compressed secp256k1 sudo public key.
+const SUDO_ADDRESS_KEY: &[u8] = b"authority/sudo_address";
+/// Merk key holding the bincode-encoded peg parameters.
+const PEG_PARAMS_KEY: &[u8] = b"authority/peg_params";
+/// Merk key holding the last applied sudo nonce.
+const SUDO_NONCE_KEY: &[u8] = b"authority/sudo_nonce";
+/// Domain separator mixed into the signed bytes so a sudo signature can never
+/// be replayed as a signature over anything else.
+const SUDO_DOMAIN: &[u8] = b"nomic/sudo/v1";
+
+/// A privileged mutation gated by the sudo key.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum SudoOp {
+    /// Set (or insert) a validator's voting power in the `validators` map.
+    SetValidatorPower { pubkey: Vec<u8>, power: u64 },
+    /// Enable or disable a validator. A disabled validator is skipped by the
+    /// `end_block` update loop. Gating this through a sudo op keeps the
+    /// `enabled` flag in consensus state so every node agrees on it.
+    SetValidatorEnabled { pubkey: Vec<u8>, enabled: bool },
+    /// Replace the stored sudo public key with a new one.
+    RotateSudoKey { new_pubkey: Vec<u8> },
+    /// Overwrite the stored peg parameters.
+    SetPegParams { params: Vec<u8> },
+}
+
+/// A sudo transaction: the signer's public key, a signature over the domain
+/// separator, the nonce and the canonical encoding of `op`, the nonce, and the
+/// operation itself.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct SudoCall {
+    pub pubkey: Vec<u8>,
+    pub signature: Vec<u8>,
+    /// Monotonic nonce; must be exactly one greater than the last applied
+    /// nonce, so an observed sudo transaction cannot be replayed.
+    pub nonce: u64,
+    pub op: SudoOp,
+}
+
+/// Read the current sudo public key, if one has been configured.
+pub fn get_sudo_address<S: Store>(store: &S) -> Result<Option<Vec<u8>>> {
+    store.get(SUDO_ADDRESS_KEY)
+}
+
+/// Store the sudo public key.
+pub fn put_sudo_address<S: Store>(store: &mut S, pubkey: Vec<u8>) -> Result<()> {
+    store.put(SUDO_ADDRESS_KEY.to_vec(), pubkey)
+}
+
+/// The last applied sudo nonce, or `0` if none has been applied yet.
+fn get_sudo_nonce<S: Store>(store: &S) -> Result<u64> {
+    match store.get(SUDO_NONCE_KEY)? {
+        Some(bytes) => {
+            let mut buf = [0u8; 8];
+            buf.copy_from_slice(&bytes[..]);
+            Ok(u64::from_be_bytes(buf))
+        }
+        None => Ok(0),
+    }
+}
+
+fn put_sudo_nonce<S: Store>(store: &mut S, nonce: u64) -> Result<()> {
+    store.put(SUDO_NONCE_KEY.to_vec(), nonce.to_be_bytes().to_vec())
+}
+
+/// Genesis config for the authority subsystem, parsed from the chain's
+/// `app_state` bytes. Without a seeded sudo key the subsystem is inert — no
+/// [`SudoCall`] can ever verify — so genesis must establish the first key.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct SudoGenesis {
+    /// Hex-encoded compressed secp256k1 sudo public key.
+    pub sudo_address: Option<String>,
+}
+
+/// Seed the sudo key from the chain's `app_state` bytes at `init_chain`. A
+/// missing or empty `app_state`, or an absent `sudo_address` field, leaves the
+/// subsystem unconfigured. Does nothing if a sudo key is already stored.
+pub fn init_from_genesis<S: Store>(store: &mut S, app_state: &[u8]) -> Result<()> {
+    if get_sudo_address(store)?.is_some() {
+        return Ok(());
+    }
+    if app_state.is_empty() {
+        return Ok(());
+    }
+    let genesis: SudoGenesis = serde_json::from_slice(app_state)
+        .map_err(|e| failure::format_err!("invalid authority genesis: {}", e))?;
+    if let Some(hex_pubkey) = genesis.sudo_address {
+        let pubkey = hex::decode(&hex_pubkey)
+            .map_err(|e| failure::format_err!("invalid sudo_address hex: {}", e))?;
+        PublicKey::from_slice(&pubkey)
+            .map_err(|e| failure::format_err!("invalid sudo_address pubkey: {}", e))?;
+        put_sudo_address(store, pubkey)?;
+    }
+    Ok(())
+}
+
+impl SudoCall {
+    /// Canonical bytes that the signature commits to: the domain separator, the
+    /// nonce, and the operation. Committing to the nonce makes an observed sudo
+    /// transaction non-replayable.
+    fn signed_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(SUDO_DOMAIN);
+        bytes.extend_from_slice(&self.nonce.to_be_bytes());
+        bytes.extend_from_slice(&bincode::serialize(&self.op).expect("Failed to serialize sudo op"));
+        bytes
+    }
+
+    /// Verify that this call is signed by the stored sudo key and carries the
+    /// next expected nonce. Returns an error if no sudo key is configured, the
+    /// signer is not the sudo holder, the nonce is stale, or the signature does
+    /// not verify. `cached_pubkey` is the decompressed signer key if it is
+    /// already held in the validator pubkey cache, letting us skip re-parsing
+    /// the secp256k1 point.
+    pub fn verify<S: Store>(&self, store: &S, cached_pubkey: Option<PublicKey>) -> Result<()> {
+        let sudo = match get_sudo_address(store)? {
+            Some(sudo) => sudo,
+            None => bail!("no sudo address configured"),
+        };
+        ensure!(self.pubkey == sudo, "signer is not the sudo key holder");
+
+        let expected = get_sudo_nonce(store)? + 1;
+        ensure!(
+            self.nonce == expected,
+            "stale sudo nonce: expected {}, got {}",
+            expected,
+            self.nonce
+        );
+
+        let pubkey = match cached_pubkey {
+            Some(pubkey) => pubkey,
+            None => PublicKey::from_slice(&self.pubkey)
+                .map_err(|e| failure::format_err!("invalid sudo pubkey: {}", e))?,
+        };
+        let signature = Signature::from_compact(&self.signature)
+            .map_err(|e| failure::format_err!("invalid sudo signature: {}", e))?;
+        let digest = Sha256::digest(&self.signed_bytes());
+        let message = Message::from_slice(&digest)
+            .map_err(|e| failure::format_err!("invalid sudo message: {}", e))?;
+
+        Secp256k1::verification_only()
+            .verify(&message, &signature, &pubkey)
+            .map_err(|e| failure::format_err!("sudo signature verification failed: {}", e))?;
+        Ok(())
+    }
+
+    /// Verify and apply this call, mutating the validator map and/or store.
+    /// `cached_pubkey` is the decompressed signer key if already cached.
+    pub fn apply<S: Store>(
+        &self,
+        store: &mut S,
+        validators: &mut BTreeMap<Vec<u8>, u64>,
+        cached_pubkey: Option<PublicKey>,
+    ) -> Result<()> {
+        self.verify(store, cached_pubkey)?;
+        // Burn the nonce before applying so a replay of this exact call is
+        // rejected on its next delivery.
+        put_sudo_nonce(store, self.nonce)?;
+        match &self.op {
+            SudoOp::SetValidatorPower { pubkey, power } => {
+                if *power == 0 {
+                    validators.remove(pubkey);
+                } else {
+                    validators.insert(pubkey.clone(), *power);
+                }
+            }
+            SudoOp::SetValidatorEnabled { pubkey, enabled } => {
+                super::admin::set_enabled(store, pubkey, *enabled)?;
+            }
+            SudoOp::RotateSudoKey { new_pubkey } => {
+                put_sudo_address(store, new_pubkey.clone())?;
+            }
+            SudoOp::SetPegParams { params } => {
+                store.put(PEG_PARAMS_KEY.to_vec(), params.clone())?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,152 @@
+//! Persistent cache of validator public keys keyed by consensus index.
+//!
+//! This module keeps two in-memory maps — `index -> decompressed_pubkey` and
+//! `pubkey_bytes -> index` — so signature checks reuse an already-parsed
+//! secp256k1 point instead of decompressing it again, and `end_block` takes
+//! the consensus-index order of the set from here. The maps are backed by a
+//! dedicated Merk key namespace (`pubkey_cache/...`) so they survive process
+//! restarts. The cache holds keys only, not voting power: the ABCI handlers
+//! still load and deserialize the whole validator map from the store on each
+//! `check_tx`, `deliver_tx`, `begin_block` and `end_block`.
+
+use crate::Result;
+use orga::Store;
+use secp256k1::PublicKey;
+use std::collections::HashMap;
+
+/// Namespace prefix for the `index -> compressed pubkey bytes` entries.
+const INDEX_PREFIX: &[u8] = b"pubkey_cache/index/";
+/// Namespace prefix for the `pubkey bytes -> index` entries.
+const PUBKEY_PREFIX: &[u8] = b"pubkey_cache/pubkey/";
+/// Key holding the next consensus index to hand out.
+const LEN_KEY: &[u8] = b"pubkey_cache/len";
+
+/// Two-way cache mapping validator consensus indices to their decompressed
+/// secp256k1 public keys.
+pub struct ValidatorPubkeyCache {
+    index_to_pubkey: Vec<PublicKey>,
+    index_to_bytes: Vec<Vec<u8>>,
+    pubkey_to_index: HashMap<Vec<u8>, u64>,
+}
+
+impl ValidatorPubkeyCache {
+    /// Load any previously-persisted entries from the store into memory.
+    pub fn load<S: Store>(store: &S) -> Result<Self> {
+        let mut cache = ValidatorPubkeyCache {
+            index_to_pubkey: Vec::new(),
+            index_to_bytes: Vec::new(),
+            pubkey_to_index: HashMap::new(),
+        };
+
+        let len = read_len(store)?;
+        for index in 0..len {
+            let bytes = store
+                .get(&index_key(index))?
+                .expect("pubkey cache index entry missing");
+            cache.insert_in_memory(index, bytes)?;
+        }
+
+        Ok(cache)
+    }
+
+    /// Append every not-yet-seen validator, persisting new entries to the
+    /// store. Validators already present are left untouched, so a steady-state
+    /// block that re-imports the current set performs no writes.
+    pub fn import_new_pubkeys<S: Store>(
+        &mut self,
+        store: &mut S,
+        pubkeys: impl IntoIterator<Item = Vec<u8>>,
+    ) -> Result<()> {
+        for bytes in pubkeys {
+            if self.pubkey_to_index.contains_key(&bytes) {
+                continue;
+            }
+            let index = self.index_to_bytes.len() as u64;
+            store.put(index_key(index), bytes.clone())?;
+            store.put(pubkey_key(&bytes), index.to_be_bytes().to_vec())?;
+            self.insert_in_memory(index, bytes)?;
+            write_len(store, self.index_to_bytes.len() as u64)?;
+        }
+        Ok(())
+    }
+
+    /// Decompressed public key for a consensus index, if known.
+    pub fn get_pubkey(&self, index: u64) -> Option<&PublicKey> {
+        self.index_to_pubkey.get(index as usize)
+    }
+
+    /// Raw compressed bytes for a consensus index, if known.
+    pub fn get_pubkey_bytes(&self, index: u64) -> Option<&[u8]> {
+        self.index_to_bytes.get(index as usize).map(Vec::as_slice)
+    }
+
+    /// Consensus index for a raw compressed public key, if known.
+    pub fn get_index(&self, pubkey_bytes: &[u8]) -> Option<u64> {
+        self.pubkey_to_index.get(pubkey_bytes).copied()
+    }
+
+    /// Decompressed public key for raw compressed bytes, if cached. Lets the
+    /// signature-verification paths reuse an already-parsed secp256k1 point
+    /// instead of decompressing it again.
+    pub fn get_pubkey_for(&self, pubkey_bytes: &[u8]) -> Option<PublicKey> {
+        let index = self.get_index(pubkey_bytes)?;
+        self.get_pubkey(index).copied()
+    }
+
+    /// Number of validators currently held in the cache.
+    pub fn len(&self) -> u64 {
+        self.index_to_bytes.len() as u64
+    }
+
+    /// Whether the cache holds no validators.
+    pub fn is_empty(&self) -> bool {
+        self.index_to_bytes.is_empty()
+    }
+
+    /// Iterate `(index, compressed pubkey bytes)` pairs in consensus-index
+    /// order. Lets callers build validator updates without reloading and
+    /// re-decompressing the whole set from the store.
+    pub fn iter(&self) -> impl Iterator<Item = (u64, &[u8])> {
+        self.index_to_bytes
+            .iter()
+            .enumerate()
+            .map(|(index, bytes)| (index as u64, bytes.as_slice()))
+    }
+
+    fn insert_in_memory(&mut self, index: u64, bytes: Vec<u8>) -> Result<()> {
+        let pubkey = PublicKey::from_slice(&bytes)
+            .map_err(|e| failure::format_err!("invalid validator pubkey: {}", e))?;
+        debug_assert_eq!(index as usize, self.index_to_bytes.len());
+        self.index_to_pubkey.push(pubkey);
+        self.pubkey_to_index.insert(bytes.clone(), index);
+        self.index_to_bytes.push(bytes);
+        Ok(())
+    }
+}
+
+fn index_key(index: u64) -> Vec<u8> {
+    let mut key = INDEX_PREFIX.to_vec();
+    key.extend_from_slice(&index.to_be_bytes());
+    key
+}
+
+fn pubkey_key(pubkey_bytes: &[u8]) -> Vec<u8> {
+    let mut key = PUBKEY_PREFIX.to_vec();
+    key.extend_from_slice(pubkey_bytes);
+    key
+}
+
+fn read_len<S: Store>(store: &S) -> Result<u64> {
+    match store.get(LEN_KEY)? {
+        Some(bytes) => {
+            let mut len_bytes = [0u8; 8];
+            len_bytes.copy_from_slice(&bytes[..]);
+            Ok(u64::from_be_bytes(len_bytes))
+        }
+        None => Ok(0),
+    }
+}
+
+fn write_len<S: Store>(store: &mut S, len: u64) -> Result<()> {
+    store.put(LEN_KEY.to_vec(), len.to_be_bytes().to_vec())
+}